@@ -1,14 +1,24 @@
+extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
 use duplicate::duplicate_item;
-use serde_json::{Number, Value};
-use std::borrow::{Borrow, Cow};
-use std::collections::BTreeMap;
-use std::hash::{BuildHasher, Hash};
-use std::io::Write;
-use std::ops::Deref;
-use std::rc::Rc;
-use std::sync::Arc;
 
-use std::{collections::HashMap, fmt::Debug};
+#[cfg(feature = "std")]
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use serde_json::{Number, Value};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 pub enum ContextType {
     Object, // Hashmaps and Structs
@@ -19,6 +29,72 @@ pub enum ContextType {
     Null
 }
 
+/// Error produced when writing to an [`Encoder`] fails.
+#[derive(Debug)]
+pub struct Error;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to write template output")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Output sink for [`ContextTrait::render`].
+///
+/// Crate-local stand-in for `std::io::Write`, which doesn't exist under
+/// `#![no_std]`.
+pub trait Encoder {
+    fn write_str(&mut self, s: &str) -> Result<(), Error>;
+
+    /// Writes `s` with `& < > " '` escaped, so it's safe to embed in HTML.
+    fn write_escaped(&mut self, s: &str) -> Result<(), Error> {
+        let mut last = 0;
+        for (index, byte) in s.bytes().enumerate() {
+            let entity = match byte {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                b'\'' => "&#39;",
+                _ => continue,
+            };
+            if last < index {
+                self.write_str(&s[last..index])?;
+            }
+            self.write_str(entity)?;
+            last = index + 1;
+        }
+        self.write_str(&s[last..])
+    }
+
+    /// Writes `s` verbatim, without escaping.
+    #[inline]
+    fn write_unescaped(&mut self, s: &str) -> Result<(), Error> {
+        self.write_str(s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Encoder for W {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        self.write_all(s.as_bytes()).map_err(|_| Error)
+    }
+}
+
+/// Adapts an [`Encoder`] so `Display` values can be formatted into it with
+/// the `write!` macro.
+struct FmtAdapter<'a>(&'a mut dyn Encoder);
+
+impl<'a> fmt::Write for FmtAdapter<'a> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_str(s).map_err(|_| fmt::Error)
+    }
+}
 
 /// A Trait for any type, that can be used as Context
 pub trait ContextTrait: Debug {
@@ -36,26 +112,30 @@ pub trait ContextTrait: Debug {
 
     // By default don't render anything. e.g. for Hashmaps
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
+    fn render(&self, _write: &mut dyn Encoder) -> Result<(), Error> {
         Ok(())
     }
 
-    /// Returns the value by a given dotted pointer.
-    // TODO get(&self, key: dyn INTO<String>) -> Option<&dyn ContextTrait>
+    /// Renders without HTML-escaping, bypassing whatever escaping `render`
+    /// applies by default. Used for `#[tera(unescaped)]` derive fields.
+    /// Defaults to [`ContextTrait::render`], which is already correct for
+    /// types (numbers, collections, ...) that don't escape in the first place.
     #[inline]
-    fn pointer(&self, key: &str) -> Option<Arc<&dyn ContextTrait>>
-where
-        Self: Sized,
-    {
-        if key == "." || key.is_empty() {
-            return Some(Arc::new(self));
-        }
+    fn render_unescaped(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        self.render(write)
+    }
+
+    /// Returns the direct child for a single (non-dotted) path segment, if any.
+    /// Used by `dyn ContextTrait`'s `pointer` method to walk a dotted path one
+    /// segment at a time.
+    #[inline]
+    fn get(&self, _key: &str) -> Option<&dyn ContextTrait> {
         None
     }
 
     /// Returns an iterator over (key,values) if possible, otherwise Option::None
     #[inline]
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String,&dyn ContextTrait)>>> {
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
         None
     }
 
@@ -70,6 +150,45 @@ where
     /// len() for arrays and hashmaps,
     /// the amount of fields in structs
     fn len(&self) -> usize;
+
+    /// Returns whether [`ContextTrait::len`] is `0`.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> dyn ContextTrait + 'a {
+    /// Returns the value by a given dotted pointer, e.g. `user.address.0.city`.
+    ///
+    /// Resolves one segment at a time via [`ContextTrait::get`]: `key` is split
+    /// at the first `.`, the head segment is looked up with `get`, and if a
+    /// tail remains the lookup recurses into the child. Implementors only need
+    /// to provide `get`; this handles the recursive traversal.
+    ///
+    /// This lives on `dyn ContextTrait` rather than as a trait method because
+    /// a default trait method cannot coerce a generic `&Self` into `&dyn
+    /// ContextTrait` (that coercion requires `Self: Sized`, which would make
+    /// the method uncallable through the `&dyn ContextTrait` this very
+    /// recursion relies on); callers holding a concrete, sized context can
+    /// reach it with `(&value as &dyn ContextTrait).pointer(key)`.
+    pub fn pointer(&self, key: &str) -> Option<Rc<&dyn ContextTrait>> {
+        if key == "." || key.is_empty() {
+            return Some(Rc::new(self));
+        }
+
+        let (head, tail) = match key.split_once('.') {
+            Some((head, tail)) => (head, tail),
+            None => (key, ""),
+        };
+
+        let child = self.get(head)?;
+        if tail.is_empty() {
+            Some(Rc::new(child))
+        } else {
+            child.pointer(tail)
+        }
+    }
 }
 
 #[duplicate_item(
@@ -93,7 +212,7 @@ impl ContextTrait for number_type {
     #[inline]
     fn is_truthy(&self) -> bool {
         // Floats shoudn't be directly compared to 0
-        *self != 0 as number_type
+        *self != number_type::default()
     }
 
     #[inline]
@@ -102,8 +221,9 @@ impl ContextTrait for number_type {
     }
 
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
-        write!(write, "{}", self)
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        use core::fmt::Write as _;
+        write!(FmtAdapter(write), "{}", self).map_err(|_| Error)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -129,19 +249,33 @@ impl<T: ContextTrait, U: Debug + Clone> ContextTrait for Result<T, U> {
         }
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
         match self {
             Ok(inner) => inner.render(write),
             _ => Ok(()),
         }
     }
-   fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
+    #[inline]
+    fn render_unescaped(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        match self {
+            Ok(inner) => inner.render_unescaped(write),
+            _ => Ok(()),
+        }
+    }
+   fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
         match self {
             Ok(inner) => inner.context_iter(),
             _ => None,
         }
     }
     #[inline]
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        match self {
+            Ok(inner) => inner.get(key),
+            _ => None,
+        }
+    }
+    #[inline]
     fn get_type(&self) -> ContextType {
         match self {
             Ok(inner) => inner.get_type(),
@@ -168,8 +302,9 @@ impl ContextTrait for bool {
         5
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
-        write!(write, "{}", self)
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        use core::fmt::Write as _;
+        write!(FmtAdapter(write), "{}", self).map_err(|_| Error)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -183,10 +318,10 @@ impl ContextTrait for bool {
 
 #[duplicate_item(
   str_type;
-  [ &'a str ];
+  [ &str ];
   [ String ];
 )]
-impl<'a> ContextTrait for str_type {
+impl ContextTrait for str_type {
     #[inline]
     fn is_truthy(&self) -> bool {
         !self.is_empty()
@@ -197,8 +332,12 @@ impl<'a> ContextTrait for str_type {
         self.len()
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
-        write!(write, "{}", self)
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        write.write_escaped(self)
+    }
+    #[inline]
+    fn render_unescaped(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        write.write_unescaped(self)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -206,7 +345,7 @@ impl<'a> ContextTrait for str_type {
     }
     #[inline]
     fn len(&self) -> usize {
-        self.len()
+        str::len(self)
     }
 }
 
@@ -225,6 +364,7 @@ impl ContextTrait for () {
     }
 }
 
+#[cfg(feature = "std")]
 impl ContextTrait for Value {
     #[inline]
     fn is_truthy(&self) -> bool {
@@ -254,49 +394,28 @@ impl ContextTrait for Value {
             Value::Null => 0,
             Value::String(ref i) => i.render_capacity_hint(),
             Value::Array(ref i) => i.render_capacity_hint(),
-            Value::Object(ref i) => 0,
+            Value::Object(ref _i) => 0,
+        }
+    }
+    #[inline]
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        match self {
+            Value::String(s) => write.write_escaped(s),
+            _ => {
+                use core::fmt::Write as _;
+                write!(FmtAdapter(write), "{}", self).map_err(|_| Error)
+            }
         }
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
-        write!(write, "{}", self)
-
-        // match *self {
-        //     Value::String(ref s) => write!(write, "{}", s),
-        //     Value::Number(ref i) => {
-        //         if let Some(v) = i.as_i64() {
-        //             write!(write, "{}", v)
-        //         } else if let Some(v) = i.as_u64() {
-        //             write!(write, "{}", v)
-        //         } else if let Some(v) = i.as_f64() {
-        //             write!(write, "{}", v)
-        //         } else {
-        //             unreachable!()
-        //         }
-        //     }
-        //     Value::Bool(i) => write!(write, "{}", i),
-        //     Value::Null => Ok(()),
-        //     Value::Array(ref a) => {
-        //         let mut first = true;
-        //         write!(write, "[")?;
-        //         for i in a.iter() {
-        //             if !first {
-        //                 write!(write, ", ")?;
-        //             }
-        //             first = false;
-        //             i.render(write)?;
-        //         }
-        //         write!(write, "]")?;
-        //         Ok(())
-        //     }
-        //     Value::Object(_) => write!(write, "[object]"),
-        // }
-    }
-
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
-        // if let Some(array) = self.as_array() {
-        //     Some(&array.iter().into())
-        // } else
+    fn render_unescaped(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        match self {
+            Value::String(s) => write.write_unescaped(s),
+            _ => self.render(write),
+        }
+    }
+
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
         if let Some(object) = self.as_object() {
             Some(Box::new(object.iter().map(|(key,value)| (key.to_string(),value as &dyn ContextTrait))))
         } else {
@@ -304,6 +423,17 @@ impl ContextTrait for Value {
         }
     }
     #[inline]
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        match self {
+            Value::Array(array) => {
+                let index = key.parse::<usize>().ok()?;
+                <[Value]>::get(array, index).map(|value| value as &dyn ContextTrait)
+            }
+            Value::Object(object) => object.get(key).map(|value| value as &dyn ContextTrait),
+            _ => None,
+        }
+    }
+    #[inline]
     fn get_type(&self) -> ContextType {
         match *self {
             Value::Number(_) => ContextType::Number,
@@ -327,6 +457,7 @@ impl ContextTrait for Value {
     }
 }
 
+#[cfg(feature = "std")]
 impl ContextTrait for Number {
     #[inline]
     fn is_truthy(&self) -> bool {
@@ -348,8 +479,9 @@ impl ContextTrait for Number {
         }
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
-        write!(write, "{}", self)
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        use core::fmt::Write as _;
+        write!(FmtAdapter(write), "{}", self).map_err(|_| Error)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -374,21 +506,35 @@ impl<T: ContextTrait> ContextTrait for Option<T> {
         }
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
         match self {
             Some(inner) => inner.render(write),
             _ => Ok(()),
         }
     }
+    #[inline]
+    fn render_unescaped(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        match self {
+            Some(inner) => inner.render_unescaped(write),
+            _ => Ok(()),
+        }
+    }
 
     #[inline]
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
         match self {
             Some(inner) => inner.context_iter(),
             _ => None,
         }
     }
     #[inline]
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        match self {
+            Some(inner) => inner.get(key),
+            _ => None,
+        }
+    }
+    #[inline]
     fn get_type(&self) -> ContextType {
         match self {
             Some(inner) => inner.get_type(),
@@ -419,8 +565,13 @@ impl<T: ContextTrait> ContextTrait for array_type {
         self.iter().map(|item| item.render_capacity_hint()).sum()
     }
     #[inline]
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
-        Some(Box::new(self.into_iter().enumerate().map(|(index,item)| (index.to_string(), item as &dyn ContextTrait))))
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
+        Some(Box::new(self.iter().enumerate().map(|(index,item)| (index.to_string(), item as &dyn ContextTrait))))
+    }
+    #[inline]
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        let index = key.parse::<usize>().ok()?;
+        <[T]>::get(self, index).map(|item| item as &dyn ContextTrait)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -432,22 +583,6 @@ impl<T: ContextTrait> ContextTrait for array_type {
     }
 }
 
-// impl<K, V> ContextTrait for (K, V)
-// where
-//     K: Borrow<str> + Debug,
-//     V: ContextTrait,
-// {
-//     #[inline]
-//     fn is_truthy(&self) -> bool {
-//         self.1.is_truthy()
-//     }
-//     #[inline]
-//     fn render_capacity_hint(&self) -> usize {
-//         self.1.render_capacity_hint()
-//     }
-// }
-
-
 impl<K, V> ContextTrait for (K, V)
 where
     K: Borrow<str> + Debug,
@@ -471,9 +606,10 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<K, V, S> ContextTrait for HashMap<K, V, S>
 where
-    K: Borrow<str> + Hash + Eq + Debug + Into<String>,
+    K: Borrow<str> + Hash + Eq + Debug,
     V: ContextTrait,
     S: BuildHasher,
 {
@@ -484,11 +620,15 @@ where
 
     #[inline]
     fn render_capacity_hint(&self) -> usize {
-        self.iter().map(|(_key, value)| value.render_capacity_hint()).sum()
+        self.values().map(|value| value.render_capacity_hint()).sum()
     }
     #[inline]
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
-        Some(Box::new(self.iter().map(|(key,value)| (key.into(), value as &dyn ContextTrait))))
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
+        Some(Box::new(self.iter().map(|(key,value)| (key.borrow().to_string(), value as &dyn ContextTrait))))
+    }
+    #[inline]
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        self.get(key).map(|value| value as &dyn ContextTrait)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -502,7 +642,7 @@ where
 
 impl<K, V> ContextTrait for BTreeMap<K, V>
 where
-    K: Borrow<str> + Ord + Debug + Into<String>,
+    K: Borrow<str> + Ord + Debug,
     V: ContextTrait,
 {
     #[inline]
@@ -512,23 +652,17 @@ where
 
     #[inline]
     fn render_capacity_hint(&self) -> usize {
-        self.iter().map(|(_key, value)| value.render_capacity_hint()).sum()
+        self.values().map(|value| value.render_capacity_hint()).sum()
     }
 
     #[inline]
-    fn pointer(&self, key: &str) -> Option<Arc<&dyn ContextTrait>>
-    where
-        Self: Sized,
-    {
-        self.get(key).map(|value| Arc::new(value as &dyn ContextTrait))
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        self.get(key).map(|value| value as &dyn ContextTrait)
     }
 
     #[inline]
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
-        Some(Box::new(self.iter().map(|(key,value)|{
-            let key_string: String = *key.into();
-            (key_string, value as &dyn ContextTrait)
-        })))
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
+        Some(Box::new(self.iter().map(|(key,value)| (key.borrow().to_string(), value as &dyn ContextTrait))))
     }
     #[inline]
     fn get_type(&self) -> ContextType {
@@ -542,38 +676,46 @@ where
 
 #[duplicate_item(
   pointer_type;
-  [ &'a T ];
+  [ &T ];
   [ Box<T> ];
   [ Rc<T> ];
   [ Arc<T> ];
 )]
-impl<'a, T> ContextTrait for pointer_type
+impl<T> ContextTrait for pointer_type
 where
     T: ContextTrait,
 {
     #[inline]
     fn is_truthy(&self) -> bool {
-        self.deref().is_truthy()
+        T::is_truthy(self)
     }
 
     #[inline]
     fn render_capacity_hint(&self) -> usize {
-        self.deref().render_capacity_hint()
+        T::render_capacity_hint(self)
+    }
+    #[inline]
+    fn render(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        T::render(self, write)
+    }
+    #[inline]
+    fn render_unescaped(&self, write: &mut dyn Encoder) -> Result<(), Error> {
+        T::render_unescaped(self, write)
     }
     #[inline]
-    fn render(&self, write: &mut dyn Write) -> std::io::Result<()> {
-        self.deref().render(write)
+    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)> + '_>> {
+        T::context_iter(self)
     }
     #[inline]
-    fn context_iter(&self) -> Option<Box<dyn Iterator<Item = (String, &dyn ContextTrait)>>> {
-        self.deref().context_iter()
+    fn get(&self, key: &str) -> Option<&dyn ContextTrait> {
+        T::get(self, key)
     }
     #[inline]
     fn get_type(&self) -> ContextType {
-        self.deref().get_type()
+        T::get_type(self)
     }
     #[inline]
     fn len(&self) -> usize {
-        self.deref().len()
+        T::len(self)
     }
 }
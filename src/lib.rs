@@ -0,0 +1,11 @@
+//! Core traits for rendering and navigating template contexts.
+//!
+//! Built on `core`/`alloc` so it works under `#![no_std]`; enable the
+//! default-on `std` feature for `serde_json::Value`/`Number` and `HashMap`
+//! support.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod context_trait;
+
+pub use context_trait::*;
+pub use tera_derive::Content;
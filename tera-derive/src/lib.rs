@@ -4,7 +4,6 @@
 extern crate proc_macro;
 
 use bae::FromAttributes;
-use fnv::FnvHasher;
 use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
@@ -12,43 +11,25 @@ use syn::punctuated::Punctuated;
 use syn::token::Comma;
 use syn::{Fields, ItemStruct, LitInt, LitStr, Path};
 
-use std::cmp::Ordering;
-use std::hash::{Hash, Hasher};
-
 type UnitFields = Punctuated<syn::Field, Comma>;
 
 struct Field {
-    hash: u64,
+    name: String,
     field: TokenStream2,
     callback: Option<Path>,
+    unescaped: bool,
 }
 
-impl PartialEq for Field {
-    fn eq(&self, other: &Field) -> bool {
-        self.hash == other.hash
-    }
-}
-
-impl Eq for Field {}
-
-impl PartialOrd for Field {
-    fn partial_cmp(&self, other: &Field) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for Field {
-    fn cmp(&self, other: &Field) -> Ordering {
-        self.hash.cmp(&other.hash)
-    }
-}
-
+// `bae` derives `from_attributes` for the attribute named after this
+// struct in snake_case, so it has to be called `Tera` to match the
+// `#[tera(...)]` surface registered below in `attributes(tera)`.
 #[derive(FromAttributes)]
-struct ContentAttributes {
+struct Tera {
     skip: Option<()>,
     flatten: Option<()>,
     rename: Option<LitStr>,
     callback: Option<Path>,
+    unescaped: Option<()>,
 }
 
 #[proc_macro_derive(Content, attributes(tera))]
@@ -56,8 +37,6 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
     let item: ItemStruct =
         syn::parse(input).expect("#[derive(Content)] can be only applied to structs");
 
-    // panic!("{:#?}", item);
-
     let name = &item.ident;
     let generics = &item.generics;
     let type_params = item.generics.type_params();
@@ -72,14 +51,15 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
     };
 
     let mut flatten = Vec::new();
-    let mut fields = fields
+    let fields = fields
         .enumerate()
         .filter_map(|(index, field)| {
             let mut callback = None;
             let mut rename = None;
             let mut skip = false;
+            let mut unescaped = false;
 
-            match ContentAttributes::try_from_attributes(&field.attrs) {
+            match Tera::try_from_attributes(&field.attrs) {
                 Ok(Some(content_attributes)) => {
                     if content_attributes.skip.is_some() {
                         skip = true;
@@ -101,6 +81,9 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
                     if let Some(path) = content_attributes.callback {
                         callback = Some(path);
                     }
+                    if content_attributes.unescaped.is_some() {
+                        unescaped = true;
+                    }
                 }
                 Ok(None) => (),
                 Err(err) => errors.push(err),
@@ -123,11 +106,7 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
                 },
             );
 
-            let mut hasher = FnvHasher::default();
-            name.hash(&mut hasher);
-            let hash = hasher.finish();
-
-            Some(Field { hash, field, callback })
+            Some(Field { name, field, callback, unescaped })
         })
         .collect::<Vec<_>>();
 
@@ -141,47 +120,97 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
         .into();
     }
 
-    fields.sort_unstable();
+    let field_count = fields.len();
+    let flatten = &*flatten;
 
-    let render = fields.iter().map(|Field { field, hash, callback, .. }| {
+    let render_fields = fields.iter().map(|Field { field, callback, unescaped, .. }| {
         if let Some(callback) = callback {
-            quote! {
-                #hash => #callback(&self.#field, encoder).map(|_| true),
-            }
+            quote! { #callback(&self.#field, write)?; }
+        } else if *unescaped {
+            quote! { self.#field.render_unescaped(write)?; }
         } else {
-            quote! {
-                #hash => self.#field.render(encoder).map(|_| true),
-            }
+            quote! { self.#field.render(write)?; }
         }
     });
 
-    let flatten = &*flatten;
-    let fields = fields.iter().map(|Field { field, .. }| field);
+    let render_unescaped_fields = fields.iter().map(|Field { field, callback, .. }| {
+        if let Some(callback) = callback {
+            quote! { #callback(&self.#field, write)?; }
+        } else {
+            quote! { self.#field.render_unescaped(write)?; }
+        }
+    });
+
+    let capacity_fields = fields.iter().map(|Field { field, .. }| {
+        quote! { self.#field.render_capacity_hint() }
+    });
+
+    let iter_entries = fields.iter().map(|Field { field, name, .. }| {
+        quote! { (::alloc::string::ToString::to_string(#name), &self.#field as &dyn ::tera::ContextTrait) }
+    });
+
+    let get_arms = fields.iter().map(|Field { field, name, .. }| {
+        quote! { #name => return Some(&self.#field as &dyn ::tera::ContextTrait), }
+    });
 
     let where_clause =
-        type_params.map(|param| quote!(#param: Content)).collect::<Vec<_>>();
+        type_params.map(|param| quote!(#param: ::tera::ContextTrait)).collect::<Vec<_>>();
     let where_clause =
         if !where_clause.is_empty() { quote!(where #(#where_clause),*) } else { quote!() };
 
-    // FIXME: decouple lifetimes from actual generics with trait boundaries
     let tokens = quote! {
-        impl #generics Content for #name #generics #where_clause {
+        impl #generics ::tera::ContextTrait for #name #generics #where_clause {
             #[inline]
-            fn capacity_hint(&self) -> usize {
-                0 #( + self.#fields.capacity_hint(tpl) )*
+            fn is_truthy(&self) -> bool {
+                ::tera::ContextTrait::len(self) > 0
             }
 
-
             #[inline]
-            fn render(&self, write: &mut dyn Write) -> std::io::Result<()>
-            {
-                match hash {
-                    #( #render )*
-                    _ => Ok(
-                        #( self.#flatten.render(write)? ||)*
-                        false
-                    )
+            fn render_capacity_hint(&self) -> usize {
+                0 #( + #capacity_fields )* #( + self.#flatten.render_capacity_hint() )*
+            }
+
+            fn render(&self, write: &mut dyn ::tera::Encoder) -> Result<(), ::tera::Error> {
+                #( #render_fields )*
+                #( self.#flatten.render(write)?; )*
+                Ok(())
+            }
+
+            fn render_unescaped(&self, write: &mut dyn ::tera::Encoder) -> Result<(), ::tera::Error> {
+                #( #render_unescaped_fields )*
+                #( self.#flatten.render_unescaped(write)?; )*
+                Ok(())
+            }
+
+            fn context_iter(&self) -> Option<::alloc::boxed::Box<dyn Iterator<Item = (::alloc::string::String, &dyn ::tera::ContextTrait)> + '_>> {
+                Some(::alloc::boxed::Box::new(
+                    ::alloc::vec![ #(#iter_entries),* ]
+                        .into_iter()
+                        #( .chain(self.#flatten.context_iter().into_iter().flatten()) )*
+                ))
+            }
+
+            fn get(&self, key: &str) -> Option<&dyn ::tera::ContextTrait> {
+                match key {
+                    #( #get_arms )*
+                    _ => {}
                 }
+                #(
+                    if let Some(found) = self.#flatten.get(key) {
+                        return Some(found);
+                    }
+                )*
+                None
+            }
+
+            #[inline]
+            fn get_type(&self) -> ::tera::ContextType {
+                ::tera::ContextType::Object
+            }
+
+            #[inline]
+            fn len(&self) -> usize {
+                #field_count #( + self.#flatten.len() )*
             }
         }
     };
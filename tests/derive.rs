@@ -0,0 +1,75 @@
+// Renders through the blanket `Encoder for std::io::Write` impl, which is
+// only available with the (default-on) `std` feature; see
+// tests/no_std_smoke.rs for the equivalent coverage without it.
+#![cfg(feature = "std")]
+
+extern crate alloc;
+
+use tera::{Content, ContextTrait};
+
+#[derive(Debug, Content)]
+struct Address {
+    city: String,
+}
+
+#[derive(Debug, Content)]
+struct Inner {
+    #[tera(rename = "addr")]
+    address: Address,
+}
+
+#[derive(Debug, Content)]
+struct User {
+    #[tera(flatten)]
+    inner: Inner,
+    #[tera(unescaped)]
+    raw: String,
+    #[tera(skip)]
+    secret: String,
+    name: String,
+}
+
+fn user() -> User {
+    User {
+        inner: Inner { address: Address { city: "Berlin".into() } },
+        raw: "<r>".into(),
+        secret: "hide-me".into(),
+        name: "A&B".into(),
+    }
+}
+
+#[test]
+fn navigates_flattened_renamed_and_skipped_fields() {
+    let user = user();
+    let ctx = &user as &dyn ContextTrait;
+
+    // `inner` is flattened, so its (renamed) field surfaces directly on `User`.
+    assert!(ctx.get("addr").is_some());
+    assert!(ctx.get("inner").is_none());
+    assert_eq!(ctx.pointer("addr.city").unwrap().len(), 6);
+
+    // `secret` is skipped, so it's neither reachable nor counted nor iterated,
+    // even though the field itself is still a normal, directly-usable member.
+    assert_eq!(user.secret, "hide-me");
+    assert!(ctx.get("secret").is_none());
+    assert_eq!(ctx.len(), 3); // addr, raw, name
+
+    let keys: Vec<_> = ctx.context_iter().unwrap().map(|(key, _)| key).collect();
+    assert!(keys.contains(&"addr".to_string()));
+    assert!(keys.contains(&"name".to_string()));
+    assert!(!keys.contains(&"inner".to_string()));
+    assert!(!keys.contains(&"secret".to_string()));
+}
+
+#[test]
+fn renders_unescaped_fields_raw_and_others_escaped() {
+    let user = user();
+    let ctx = &user as &dyn ContextTrait;
+
+    let mut out = Vec::new();
+    ctx.render(&mut out).unwrap();
+    let rendered = String::from_utf8(out).unwrap();
+
+    assert!(rendered.contains("<r>"), "unescaped field should render raw: {rendered}");
+    assert!(rendered.contains("A&amp;B"), "other fields should still render HTML-escaped: {rendered}");
+}
@@ -0,0 +1,61 @@
+//! Exercises the parts of the crate that must work with the default-on
+//! `std` feature turned off (run via `cargo test --no-default-features`),
+//! staying clear of the `Value`/`Number`/`HashMap` impls that are
+//! intentionally gated behind it. `BTreeMap`, `Vec`, numbers, `bool`,
+//! `&str`/`String`, and `#[derive(Content)]` are all expected to keep
+//! working under `#![no_std]` + `alloc`.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use tera::{Content, ContextTrait, Encoder, Error};
+
+#[derive(Debug, Content)]
+struct Post {
+    title: String,
+    tags: Vec<String>,
+    views: u32,
+}
+
+/// The blanket `Encoder for std::io::Write` impl is gated behind the `std`
+/// feature, so a no_std consumer has to bring its own sink; this is the
+/// minimal one.
+struct StringSink(String);
+
+impl Encoder for StringSink {
+    fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        self.0.push_str(s);
+        Ok(())
+    }
+}
+
+#[test]
+fn derived_struct_works_without_std_feature() {
+    let post = Post { title: "no_std".into(), tags: alloc::vec!["rust".into(), "embedded".into()], views: 42 };
+    let ctx = &post as &dyn ContextTrait;
+
+    assert_eq!(ctx.len(), 3);
+    assert!(ctx.get("title").is_some());
+    assert_eq!(ctx.pointer("tags.1").unwrap().len(), 8); // "embedded"
+
+    // `Vec<T>` has no `render` of its own (it's a collection, not a leaf
+    // value), so only the leaf fields show up in the rendered output.
+    let mut sink = StringSink(String::new());
+    ctx.render(&mut sink).unwrap();
+    assert!(sink.0.contains("no_std"));
+    assert!(sink.0.contains("42"));
+}
+
+#[test]
+fn btreemap_context_works_without_std_feature() {
+    let mut map: BTreeMap<String, u32> = BTreeMap::new();
+    map.insert("a".into(), 1);
+    map.insert("b".into(), 2);
+    let ctx = &map as &dyn ContextTrait;
+
+    assert_eq!(ctx.len(), 2);
+    assert!(ctx.get("a").unwrap().is_truthy());
+    assert_eq!(ctx.context_iter().unwrap().count(), 2);
+}